@@ -0,0 +1,60 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Caching validators (`ETag` + `Last-Modified`) derived from a file's
+/// metadata, used to answer conditional GET requests without re-reading the
+/// file body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileValidator {
+    pub etag: String,
+    modified: SystemTime,
+}
+
+impl FileValidator {
+    /// Builds a weak `ETag` from `(size_bytes, mtime_secs, mtime_nanos)`.
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> std::io::Result<Self> {
+        let modified = metadata.modified()?;
+        let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        let etag = format!(
+            "W/\"{:x}:{:x}-{:x}\"",
+            since_epoch.as_secs(),
+            since_epoch.subsec_nanos(),
+            metadata.len()
+        );
+
+        Ok(Self { etag, modified })
+    }
+
+    pub fn last_modified_http_date(&self) -> String {
+        httpdate::fmt_http_date(self.modified)
+    }
+
+    /// Whether an `If-None-Match` header value should short-circuit to `304`.
+    pub fn satisfies_none_match(&self, header_value: &str) -> bool {
+        if header_value.trim() == "*" {
+            return true;
+        }
+        header_value.split(',').map(|v| v.trim()).any(|v| v == self.etag)
+    }
+
+    /// Whether an `If-Modified-Since` header value should short-circuit to
+    /// `304`: true when the file is not newer than the given date.
+    pub fn not_modified_since(&self, header_value: &str) -> bool {
+        match httpdate::parse_http_date(header_value) {
+            Ok(since) => self.modified <= since,
+            Err(_) => false,
+        }
+    }
+
+    /// `If-Range` semantics: true when the validator is still current, so a
+    /// Range request combined with this header should be honored rather than
+    /// falling back to a full `200`. Only the HTTP-date form is evaluated:
+    /// our ETag is always weak (`W/"..."`), and RFC 7233 §3.2 requires a
+    /// *strong* comparison for `If-Range`, so a weak ETag can never satisfy
+    /// it and an ETag-form header here always falls back to a full `200`.
+    pub fn satisfies_if_range(&self, header_value: &str) -> bool {
+        httpdate::parse_http_date(header_value)
+            .map(|since| self.modified <= since)
+            .unwrap_or(false)
+    }
+}
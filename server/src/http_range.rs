@@ -0,0 +1,85 @@
+use std::ops::RangeInclusive;
+
+/// A single byte range resolved against a resource of known length.
+///
+/// Only the single-range form of the `Range` header is supported; a
+/// comma-separated list of ranges is treated as malformed and ignored, same
+/// as an unparsable header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpRangeError {
+    /// The header didn't match `bytes=start-end` syntax; callers should fall
+    /// back to a full `200` response rather than reject the request.
+    Malformed,
+    /// The header parsed fine but doesn't address any byte of the resource.
+    Unsatisfiable,
+}
+
+impl HttpRange {
+    /// Parses a `Range: bytes=start-end` header against a resource that is
+    /// `size` bytes long.
+    ///
+    /// Supports the open-ended (`bytes=500-`) and suffix (`bytes=-500`)
+    /// forms in addition to a fully bounded range.
+    pub fn parse(header: &str, size: u64) -> Result<Self, HttpRangeError> {
+        let spec = header
+            .strip_prefix("bytes=")
+            .ok_or(HttpRangeError::Malformed)?;
+
+        if spec.contains(',') {
+            return Err(HttpRangeError::Malformed);
+        }
+
+        let (start_str, end_str) = spec.split_once('-').ok_or(HttpRangeError::Malformed)?;
+
+        let (start, end) = if start_str.is_empty() {
+            // Suffix range, e.g. `bytes=-500` means the last 500 bytes.
+            let suffix_len: u64 = end_str.parse().map_err(|_| HttpRangeError::Malformed)?;
+            if suffix_len == 0 || size == 0 {
+                return Err(HttpRangeError::Unsatisfiable);
+            }
+            (size.saturating_sub(suffix_len), size - 1)
+        } else {
+            let start: u64 = start_str.parse().map_err(|_| HttpRangeError::Malformed)?;
+            let end: u64 = if end_str.is_empty() {
+                size.saturating_sub(1)
+            } else {
+                end_str.parse().map_err(|_| HttpRangeError::Malformed)?
+            };
+            (start, end)
+        };
+
+        if start >= size || start > end {
+            return Err(HttpRangeError::Unsatisfiable);
+        }
+
+        Ok(HttpRange {
+            start,
+            end: end.min(size - 1),
+        })
+    }
+
+    /// Number of bytes covered by this range, inclusive of both ends.
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn as_inclusive(&self) -> RangeInclusive<u64> {
+        self.start..=self.end
+    }
+
+    /// Value for the `Content-Range` header of a `206 Partial Content` reply.
+    pub fn content_range_header(&self, total_size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total_size)
+    }
+
+    /// Value for the `Content-Range` header of a `416` reply.
+    pub fn unsatisfiable_content_range_header(total_size: u64) -> String {
+        format!("bytes */{}", total_size)
+    }
+}
@@ -1,20 +1,31 @@
 use actix_identity::Identity;
 use actix_web::{http, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
 use std::convert::TryFrom;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::app_state::AppState;
 use crate::error::NASError;
-use crate::file::{AbsolutePath, NASFile, NASFileCategory, RelativePath};
+use crate::file::{AbsolutePath, NASFileCategory, RelativePath};
 use crate::templates::AuthPageParams;
+use crate::trash;
 use crate::utils::strip_trailing_char;
 use crate::CONFIG;
 
+#[derive(Debug, Deserialize)]
+pub struct DeleteParams {
+    /// When true, bypasses the trash and removes the path immediately.
+    #[serde(default)]
+    pub permanent: bool,
+}
+
 pub async fn delete(
     identity: Identity,
     app_state: web::Data<AppState>,
     path: web::Path<String>,
+    params: web::Query<DeleteParams>,
 ) -> Result<impl Responder> {
     let templates = &app_state.templates;
     let identity = identity.identity();
@@ -50,13 +61,92 @@ pub async fn delete(
     let category = absolute_path.category()?;
     let pathbuf: PathBuf = absolute_path.into();
 
-    if let NASFileCategory::Directory = category {
-        fs::remove_dir_all(&pathbuf).map_err(|_| NASError::PathDeleteError { pathbuf })?;
+    let root = Path::new(&CONFIG.fs_root);
+
+    if params.permanent {
+        delete_permanently(&pathbuf, &category)?;
     } else {
-        fs::remove_file(&pathbuf).map_err(|_| NASError::PathDeleteError { pathbuf })?;
+        move_to_trash(&app_state, &username, root, &pathbuf)?;
+    }
+
+    // The index keys files by their path relative to ROOT (which includes
+    // the username component), not by the user-relative URL path, so derive
+    // the same key `reindex` would have stored before pruning it.
+    if let Ok(index_key) = pathbuf.strip_prefix(root) {
+        let _ = app_state.db.remove_path(&index_key.to_string_lossy());
     }
 
     Ok(HttpResponse::Ok()
         .header(http::header::CONTENT_TYPE, "text/html;charset=utf-8")
         .finish())
 }
+
+fn delete_permanently(pathbuf: &PathBuf, category: &NASFileCategory) -> Result<(), NASError> {
+    if let NASFileCategory::Directory = category {
+        fs::remove_dir_all(pathbuf).map_err(|_| NASError::PathDeleteError {
+            pathbuf: pathbuf.clone(),
+        })?;
+    } else {
+        fs::remove_file(pathbuf).map_err(|_| NASError::PathDeleteError {
+            pathbuf: pathbuf.clone(),
+        })?;
+    }
+
+    Ok(())
+}
+
+fn move_to_trash(
+    app_state: &web::Data<AppState>,
+    username: &str,
+    root: &Path,
+    pathbuf: &PathBuf,
+) -> Result<(), NASError> {
+    let user_trash_dir = trash::trash_dir(root, username);
+
+    let deleted_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let name = pathbuf
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+    let trash_name = format!("{}_{}", deleted_at, name);
+
+    let trash_path = trash::move_to_trash(pathbuf, &user_trash_dir, &trash_name).map_err(|_| {
+        NASError::PathDeleteError {
+            pathbuf: pathbuf.clone(),
+        }
+    })?;
+
+    // `original_relative_path` must be in the same ROOT-relative coordinate
+    // system as the index key and `trash_relative_path` below (i.e. it
+    // includes the username component), or `restore` reconstructs the wrong
+    // destination path.
+    let original_relative_path = pathbuf
+        .strip_prefix(root)
+        .unwrap_or(pathbuf)
+        .to_string_lossy()
+        .to_string();
+
+    let trash_relative_path = trash_path
+        .strip_prefix(root)
+        .unwrap_or(&trash_path)
+        .to_string_lossy()
+        .to_string();
+
+    app_state
+        .db
+        .insert_trash_entry(
+            &original_relative_path,
+            username,
+            &trash_relative_path,
+            deleted_at,
+        )
+        .map_err(|e| NASError::DatabaseError {
+            error: e.to_string(),
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use actix_identity::Identity;
+use actix_web::web::Bytes;
+use actix_web::{http, web, HttpRequest, HttpResponse, Responder, Result};
+use futures::Stream;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use crate::app_state::AppState;
+use crate::error::NASError;
+use crate::etag::FileValidator;
+use crate::file::{AbsolutePath, RelativePath};
+use crate::http_range::{HttpRange, HttpRangeError};
+use crate::templates::AuthPageParams;
+use crate::utils::strip_trailing_char;
+use crate::CONFIG;
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+type ChunkResult = std::result::Result<Bytes, actix_web::Error>;
+
+/// Forwards chunks read off a blocking thread over a channel, so the chunk
+/// reads never block the async reactor on disk I/O. The channel is
+/// unbounded and the reader does not wait for the client: when the client
+/// consumes slower than the disk is read, the queued chunks accumulate in
+/// memory, up to the whole requested range.
+struct FileChunkStream {
+    receiver: mpsc::UnboundedReceiver<ChunkResult>,
+}
+
+impl Stream for FileChunkStream {
+    type Item = ChunkResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_recv(cx)
+    }
+}
+
+/// Reads `remaining` bytes out of `file` (from its current seek position)
+/// on a blocking thread, in fixed-size chunks, sending each one over a
+/// channel as it's read.
+fn spawn_file_chunk_stream(file: File, remaining: u64) -> FileChunkStream {
+    let (sender, receiver) = mpsc::unbounded_channel::<ChunkResult>();
+
+    actix_web::rt::spawn(async move {
+        let task_sender = sender.clone();
+        let result = web::block(move || read_chunks(file, remaining, sender)).await;
+        if let Err(e) = result {
+            let _ = task_sender.send(Err(actix_web::error::ErrorInternalServerError(e)));
+        }
+    });
+
+    FileChunkStream { receiver }
+}
+
+fn read_chunks(
+    mut file: File,
+    mut remaining: u64,
+    sender: mpsc::UnboundedSender<ChunkResult>,
+) -> io::Result<()> {
+    let mut buf = [0u8; CHUNK_SIZE as usize];
+
+    while remaining > 0 {
+        let to_read = remaining.min(CHUNK_SIZE) as usize;
+        file.read_exact(&mut buf[..to_read])?;
+        remaining -= to_read as u64;
+
+        if sender
+            .send(Ok(Bytes::copy_from_slice(&buf[..to_read])))
+            .is_err()
+        {
+            break; // client disconnected
+        }
+    }
+
+    Ok(())
+}
+
+/// Serves a single file, honoring a `Range` request header so media players
+/// can seek without downloading the whole file.
+pub async fn download(
+    req: HttpRequest,
+    identity: Identity,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let templates = &app_state.templates;
+    let identity = identity.identity();
+
+    if identity.is_none() {
+        return Ok(HttpResponse::Unauthorized()
+            .header(http::header::CONTENT_TYPE, "text/html;charset=utf-8")
+            .body(
+                templates
+                    .render(
+                        "auth",
+                        &AuthPageParams {
+                            theme: CONFIG.theme.clone(),
+                            logged_in: false,
+                            message: Some("Protected resource, please log in".to_string()),
+                            redirect_url: None,
+                        },
+                    )
+                    .map_err(|e| NASError::TemplateRenderError {
+                        template: "auth".to_string(),
+                        error: e.to_string(),
+                    })?,
+            ));
+    }
+
+    let username = identity.unwrap();
+
+    // The NormalizePath middleware will add a trailing slash at the end of the path, so we must remove it
+    let relative_path_str = strip_trailing_char(&path);
+    let relative_path = RelativePath::new(&relative_path_str, &username);
+    let absolute_path = AbsolutePath::try_from(&relative_path)?;
+    let mime_type = absolute_path.mime_type()?;
+
+    let pathbuf: PathBuf = absolute_path.into();
+
+    let metadata = pathbuf
+        .metadata()
+        .map_err(|_| NASError::PathReadError {
+            pathbuf: pathbuf.clone(),
+        })?;
+    let size_bytes = metadata.len();
+
+    let validator = FileValidator::from_metadata(&metadata).map_err(|_| NASError::PathReadError {
+        pathbuf: pathbuf.clone(),
+    })?;
+
+    let header_str = |name: http::header::HeaderName| {
+        req.headers().get(name).and_then(|h| h.to_str().ok())
+    };
+
+    let not_modified = header_str(http::header::IF_NONE_MATCH)
+        .map(|v| validator.satisfies_none_match(v))
+        .or_else(|| header_str(http::header::IF_MODIFIED_SINCE).map(|v| validator.not_modified_since(v)))
+        .unwrap_or(false);
+
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .header(http::header::ETAG, validator.etag.clone())
+            .header(http::header::LAST_MODIFIED, validator.last_modified_http_date())
+            .finish());
+    }
+
+    let mut file = File::open(&pathbuf).map_err(|_| NASError::PathReadError {
+        pathbuf: pathbuf.clone(),
+    })?;
+
+    // A Range request is only honored if there's no If-Range header, or the
+    // validator it names is still current; otherwise the file changed since
+    // the client cached its previous ranges and we must serve it whole.
+    let range_header = header_str(http::header::RANGE).filter(|_| {
+        header_str(http::header::IF_RANGE)
+            .map(|v| validator.satisfies_if_range(v))
+            .unwrap_or(true)
+    });
+
+    if let Some(range_header) = range_header {
+        match HttpRange::parse(range_header, size_bytes) {
+            Ok(range) => {
+                file.seek(SeekFrom::Start(range.start))
+                    .map_err(|_| NASError::PathReadError {
+                        pathbuf: pathbuf.clone(),
+                    })?;
+
+                let stream = spawn_file_chunk_stream(file, range.len());
+
+                return Ok(HttpResponse::PartialContent()
+                    .header(http::header::ACCEPT_RANGES, "bytes")
+                    .header(
+                        http::header::CONTENT_RANGE,
+                        range.content_range_header(size_bytes),
+                    )
+                    .header(http::header::ETAG, validator.etag.clone())
+                    .header(http::header::LAST_MODIFIED, validator.last_modified_http_date())
+                    .content_type(mime_type.clone())
+                    .streaming(stream));
+            }
+            Err(HttpRangeError::Unsatisfiable) => {
+                return Ok(HttpResponse::RangeNotSatisfiable()
+                    .header(
+                        http::header::CONTENT_RANGE,
+                        HttpRange::unsatisfiable_content_range_header(size_bytes),
+                    )
+                    .finish());
+            }
+            Err(HttpRangeError::Malformed) => {
+                // Fall through and serve the full file, per RFC 7233 §3.1.
+            }
+        }
+    }
+
+    let stream = spawn_file_chunk_stream(file, size_bytes);
+
+    Ok(HttpResponse::Ok()
+        .header(http::header::ACCEPT_RANGES, "bytes")
+        .header(http::header::ETAG, validator.etag.clone())
+        .header(http::header::LAST_MODIFIED, validator.last_modified_http_date())
+        .content_type(mime_type)
+        .streaming(stream))
+}
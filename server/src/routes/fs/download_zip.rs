@@ -0,0 +1,183 @@
+use actix_identity::Identity;
+use actix_web::web::Bytes;
+use actix_web::{http, web, HttpResponse, Responder, Result};
+use futures::Stream;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use crate::app_state::AppState;
+use crate::error::NASError;
+use crate::file::{AbsolutePath, NASFileCategory, RelativePath};
+use crate::templates::AuthPageParams;
+use crate::utils::strip_trailing_char;
+use crate::zip_writer::ZipStreamWriter;
+use crate::CONFIG;
+
+type ChunkResult = Result<Bytes, actix_web::Error>;
+
+/// Streams a directory as a ZIP archive so a whole folder can be downloaded
+/// as one file, without the size having to be known up front.
+pub async fn download_zip(
+    identity: Identity,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let templates = &app_state.templates;
+    let identity = identity.identity();
+
+    if identity.is_none() {
+        return Ok(HttpResponse::Unauthorized()
+            .header(http::header::CONTENT_TYPE, "text/html;charset=utf-8")
+            .body(
+                templates
+                    .render(
+                        "auth",
+                        &AuthPageParams {
+                            theme: CONFIG.theme.clone(),
+                            logged_in: false,
+                            message: Some("Protected resource, please log in".to_string()),
+                            redirect_url: None,
+                        },
+                    )
+                    .map_err(|e| NASError::TemplateRenderError {
+                        template: "auth".to_string(),
+                        error: e.to_string(),
+                    })?,
+            ));
+    }
+
+    let username = identity.unwrap();
+
+    // The NormalizePath middleware will add a trailing slash at the end of the path, so we must remove it
+    let relative_path_str = strip_trailing_char(&path);
+    let relative_path = RelativePath::new(&relative_path_str, &username);
+    let absolute_path = AbsolutePath::try_from(&relative_path)?;
+
+    let category = absolute_path.category()?;
+    if !matches!(category, NASFileCategory::Directory) {
+        return Err(NASError::NotADirectoryError {
+            pathbuf: absolute_path.into(),
+        }
+        .into());
+    }
+
+    let root: PathBuf = absolute_path.into();
+    let dir_name = root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("download")
+        .to_string();
+
+    let (sender, receiver) = mpsc::unbounded_channel::<ChunkResult>();
+
+    // The archive is built on a blocking thread and streamed out chunk by
+    // chunk as it's produced, since the total size isn't known up front.
+    actix_web::rt::spawn(async move {
+        let task_sender = sender.clone();
+        let result = web::block(move || zip_directory(&root, sender)).await;
+        if let Err(e) = result {
+            let _ = task_sender.send(Err(actix_web::error::ErrorInternalServerError(e)));
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .header(
+            http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.zip\"", dir_name),
+        )
+        .header(http::header::CONTENT_TYPE, "application/zip")
+        .streaming(ZipChunkStream { receiver }))
+}
+
+fn zip_directory(root: &Path, sender: mpsc::UnboundedSender<ChunkResult>) -> io::Result<()> {
+    let canonical_root = root.canonicalize()?;
+    let zip_writer = ZipStreamWriter::new(ChannelWriter {
+        sender: sender.clone(),
+    });
+
+    let mut zip_writer = zip_writer;
+    walk(&canonical_root, &canonical_root, &mut zip_writer)?;
+    zip_writer.finish()?;
+
+    Ok(())
+}
+
+/// Recursively adds every regular file under `dir` to `zip`, storing paths
+/// relative to `root` with forward slashes. Entries whose canonical path
+/// escapes `root` (e.g. a symlink pointing outside it) are skipped, and
+/// directory symlinks are never followed, so a symlink cycle inside `root`
+/// can't recurse forever.
+fn walk(root: &Path, dir: &Path, zip: &mut ZipStreamWriter<ChannelWriter>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(_) => continue, // broken symlink
+        };
+        if !canonical.starts_with(root) {
+            continue;
+        }
+
+        // `file_type()` reports the entry itself, not what it points to, so
+        // a symlinked directory matches neither branch below instead of
+        // being recursed into.
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            walk(root, &path, zip)?;
+        } else if file_type.is_file() {
+            let relative = canonical
+                .strip_prefix(root)
+                .unwrap_or(&canonical)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            let file = fs::File::open(&path)?;
+            zip.add_file(&relative, file)?;
+        }
+        // Symlinks (to files or directories) are skipped: following them
+        // would risk an unbounded cycle, since `starts_with(root)` only
+        // guards against escaping the tree, not re-entering it.
+    }
+
+    Ok(())
+}
+
+/// Forwards each chunk written to it over an mpsc channel, so the ZIP
+/// encoder can run on a blocking thread while actix streams the chunks out
+/// as they're produced.
+struct ChannelWriter {
+    sender: mpsc::UnboundedSender<ChunkResult>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct ZipChunkStream {
+    receiver: mpsc::UnboundedReceiver<ChunkResult>,
+}
+
+impl Stream for ZipChunkStream {
+    type Item = ChunkResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_recv(cx)
+    }
+}
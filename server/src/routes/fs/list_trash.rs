@@ -0,0 +1,25 @@
+use actix_identity::Identity;
+use actix_web::{web, HttpResponse, Responder, Result};
+
+use crate::app_state::AppState;
+use crate::error::NASError;
+
+/// Lists the current user's trashed paths.
+pub async fn list_trash(
+    identity: Identity,
+    app_state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let username = match identity.identity() {
+        Some(username) => username,
+        None => return Ok(HttpResponse::Unauthorized().finish()),
+    };
+
+    let entries = app_state
+        .db
+        .list_trash(&username)
+        .map_err(|e| NASError::DatabaseError {
+            error: e.to_string(),
+        })?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
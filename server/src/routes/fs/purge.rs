@@ -0,0 +1,78 @@
+use actix_identity::Identity;
+use actix_web::{http, web, HttpResponse, Responder, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::app_state::AppState;
+use crate::error::NASError;
+use crate::templates::AuthPageParams;
+use crate::CONFIG;
+
+/// Permanently removes a single trashed path (and its `Trash` row).
+pub async fn purge(
+    identity: Identity,
+    app_state: web::Data<AppState>,
+    trash_id: web::Path<i64>,
+) -> Result<impl Responder> {
+    let templates = &app_state.templates;
+    let identity = identity.identity();
+
+    if identity.is_none() {
+        return Ok(HttpResponse::Unauthorized()
+            .header(http::header::CONTENT_TYPE, "text/html;charset=utf-8")
+            .body(
+                templates
+                    .render(
+                        "auth",
+                        &AuthPageParams {
+                            theme: CONFIG.theme.clone(),
+                            logged_in: false,
+                            message: Some("Protected resource, please log in".to_string()),
+                            redirect_url: None,
+                        },
+                    )
+                    .map_err(|e| NASError::TemplateRenderError {
+                        template: "auth".to_string(),
+                        error: e.to_string(),
+                    })?,
+            ));
+    }
+
+    let username = identity.unwrap();
+
+    let entry = app_state
+        .db
+        .find_trash_entry(trash_id.into_inner())
+        .map_err(|e| NASError::DatabaseError {
+            error: e.to_string(),
+        })?
+        .ok_or(NASError::TrashEntryNotFoundError)?;
+
+    if entry.username != username {
+        return Err(NASError::TrashEntryNotFoundError.into());
+    }
+
+    let root = Path::new(&CONFIG.fs_root);
+    let trash_path = root.join(&entry.trash_relative_path);
+
+    if trash_path.is_dir() {
+        fs::remove_dir_all(&trash_path).map_err(|_| NASError::PathDeleteError {
+            pathbuf: trash_path.clone(),
+        })?;
+    } else {
+        fs::remove_file(&trash_path).map_err(|_| NASError::PathDeleteError {
+            pathbuf: trash_path.clone(),
+        })?;
+    }
+
+    app_state
+        .db
+        .remove_trash_entry(entry.id)
+        .map_err(|e| NASError::DatabaseError {
+            error: e.to_string(),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .header(http::header::CONTENT_TYPE, "text/html;charset=utf-8")
+        .finish())
+}
@@ -0,0 +1,97 @@
+use anyhow::Context;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db::NASDB;
+
+/// The per-user trash directory, e.g. `<ROOT>/<username>/.trash`.
+pub fn trash_dir(root: &Path, username: &str) -> PathBuf {
+    root.join(username).join(".trash")
+}
+
+/// Moves `source` into `trash_dir` under `trash_name` (or a suffixed
+/// variant of it, if that name is already taken), renaming within the same
+/// filesystem when possible and falling back to a recursive copy + remove
+/// when `source` and `trash_dir` live on different devices.
+pub fn move_to_trash(source: &Path, trash_dir: &Path, trash_name: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(trash_dir)?;
+    let destination = unique_destination(trash_dir, trash_name);
+
+    if fs::rename(source, &destination).is_ok() {
+        return Ok(destination);
+    }
+
+    copy_recursive(source, &destination)?;
+    if source.is_dir() {
+        fs::remove_dir_all(source)?;
+    } else {
+        fs::remove_file(source)?;
+    }
+
+    Ok(destination)
+}
+
+/// Appends a numeric suffix to `trash_name` until the resulting path inside
+/// `trash_dir` doesn't already exist, so two deletes whose names collide
+/// (e.g. the same filename deleted twice within the same second) land at
+/// distinct destinations instead of the second silently overwriting the
+/// first.
+fn unique_destination(trash_dir: &Path, trash_name: &str) -> PathBuf {
+    let mut destination = trash_dir.join(trash_name);
+    let mut suffix = 1;
+
+    while destination.exists() {
+        destination = trash_dir.join(format!("{}_{}", trash_name, suffix));
+        suffix += 1;
+    }
+
+    destination
+}
+
+fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    if source.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(source, destination)?;
+    }
+
+    Ok(())
+}
+
+/// Permanently removes every trash entry older than `retention`, called
+/// periodically from a background task.
+pub fn purge_expired(db: &NASDB, root: &Path, retention: Duration) -> anyhow::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let cutoff = now.saturating_sub(retention).as_secs() as i64;
+
+    for entry in db.expired_trash_entries(cutoff)? {
+        let trash_path = root.join(&entry.trash_relative_path);
+
+        let removed = if trash_path.is_dir() {
+            fs::remove_dir_all(&trash_path)
+        } else {
+            fs::remove_file(&trash_path)
+        };
+
+        match removed {
+            Ok(()) => db.remove_trash_entry(entry.id)?,
+            // Already gone from disk somehow; still drop the stale row.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => db.remove_trash_entry(entry.id)?,
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Unable to purge expired trash entry at {:?}", trash_path)
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
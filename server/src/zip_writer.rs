@@ -0,0 +1,219 @@
+use crc32fast::Hasher as Crc32;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const VERSION_NEEDED: u16 = 20;
+const DEFLATE_METHOD: u16 = 8;
+// Bit 3: sizes and CRC-32 follow the file data in a data descriptor, since
+// we don't know them until the whole entry has been streamed through.
+const GENERAL_PURPOSE_FLAG: u16 = 0x0008;
+// The classic (non-ZIP64) format can't express an offset, size, or count
+// past this; we don't implement ZIP64 records, so entries/archives that
+// would need one are rejected rather than silently truncated/wrapped.
+const ZIP32_LIMIT: u64 = 0xFFFF_FFFF;
+
+struct CentralDirEntry {
+    name: String,
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    local_header_offset: u64,
+}
+
+fn too_large(label: &'static str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "{} exceeds the 4 GiB limit of a non-ZIP64 archive",
+            label
+        ),
+    )
+}
+
+fn as_u32(value: u64, label: &'static str) -> io::Result<u32> {
+    if value > ZIP32_LIMIT {
+        return Err(too_large(label));
+    }
+    Ok(value as u32)
+}
+
+/// A ZIP encoder that writes directly to any `Write`r as entries are added,
+/// rather than building the archive in memory first. Each entry is
+/// deflate-compressed and its size/CRC-32 are written in a trailing data
+/// descriptor, so the whole file never needs to be buffered.
+///
+/// Offsets and sizes are tracked as `u64` so a large archive doesn't wrap
+/// around internally, but the on-disk format emitted is classic (non-ZIP64)
+/// ZIP: any single entry, or the archive as a whole, that would need a field
+/// wider than 4 GiB is rejected rather than silently corrupted.
+pub struct ZipStreamWriter<W: Write> {
+    writer: CountingWriter<W>,
+    entries: Vec<CentralDirEntry>,
+}
+
+impl<W: Write> ZipStreamWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: CountingWriter::new(writer),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds one file entry, reading its contents from `reader` and streaming
+    /// the deflated bytes straight into the underlying writer.
+    pub fn add_file(&mut self, name: &str, mut reader: impl io::Read) -> io::Result<()> {
+        let local_header_offset = self.writer.count();
+        as_u32(local_header_offset, "local file header offset")?;
+        let name_bytes = name.as_bytes();
+
+        self.writer.write_u32(LOCAL_FILE_HEADER_SIGNATURE)?;
+        self.writer.write_u16(VERSION_NEEDED)?;
+        self.writer.write_u16(GENERAL_PURPOSE_FLAG)?;
+        self.writer.write_u16(DEFLATE_METHOD)?;
+        self.writer.write_u16(0)?; // last mod file time
+        self.writer.write_u16(0)?; // last mod file date
+        self.writer.write_u32(0)?; // crc-32 (in data descriptor)
+        self.writer.write_u32(0)?; // compressed size (in data descriptor)
+        self.writer.write_u32(0)?; // uncompressed size (in data descriptor)
+        self.writer.write_u16(name_bytes.len() as u16)?;
+        self.writer.write_u16(0)?; // extra field length
+        self.writer.write_all(name_bytes)?;
+
+        let data_start = self.writer.count();
+        let mut crc32 = Crc32::new();
+        let mut uncompressed_size: u64 = 0;
+        {
+            let mut encoder = DeflateEncoder::new(&mut self.writer, Compression::default());
+            let mut buf = [0u8; 64 * 1024];
+            loop {
+                let read = reader.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                crc32.update(&buf[..read]);
+                uncompressed_size += read as u64;
+                encoder.write_all(&buf[..read])?;
+            }
+            encoder.finish()?;
+        }
+
+        let crc32 = crc32.finalize();
+        let compressed_size = self.writer.count() - data_start;
+        as_u32(compressed_size, "compressed entry size")?;
+        as_u32(uncompressed_size, "uncompressed entry size")?;
+
+        self.writer.write_u32(DATA_DESCRIPTOR_SIGNATURE)?;
+        self.writer.write_u32(crc32)?;
+        self.writer.write_u32(compressed_size as u32)?;
+        self.writer.write_u32(uncompressed_size as u32)?;
+
+        self.entries.push(CentralDirEntry {
+            name: name.to_string(),
+            crc32,
+            compressed_size,
+            uncompressed_size,
+            local_header_offset,
+        });
+
+        Ok(())
+    }
+
+    /// Writes the central directory and end-of-central-directory record.
+    pub fn finish(mut self) -> io::Result<W> {
+        let central_directory_offset = self.writer.count();
+        as_u32(central_directory_offset, "central directory offset")?;
+
+        if self.entries.len() as u64 > 0xFFFF {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "entry count exceeds the 65535-entry limit of a non-ZIP64 archive",
+            ));
+        }
+
+        for entry in &self.entries {
+            let name_bytes = entry.name.as_bytes();
+
+            self.writer.write_u32(CENTRAL_DIRECTORY_SIGNATURE)?;
+            self.writer.write_u16(VERSION_NEEDED)?; // version made by
+            self.writer.write_u16(VERSION_NEEDED)?; // version needed to extract
+            self.writer.write_u16(GENERAL_PURPOSE_FLAG)?;
+            self.writer.write_u16(DEFLATE_METHOD)?;
+            self.writer.write_u16(0)?; // last mod file time
+            self.writer.write_u16(0)?; // last mod file date
+            self.writer.write_u32(entry.crc32)?;
+            self.writer.write_u32(entry.compressed_size as u32)?;
+            self.writer.write_u32(entry.uncompressed_size as u32)?;
+            self.writer.write_u16(name_bytes.len() as u16)?;
+            self.writer.write_u16(0)?; // extra field length
+            self.writer.write_u16(0)?; // file comment length
+            self.writer.write_u16(0)?; // disk number start
+            self.writer.write_u16(0)?; // internal file attributes
+            self.writer.write_u32(0)?; // external file attributes
+            self.writer.write_u32(entry.local_header_offset as u32)?;
+            self.writer.write_all(name_bytes)?;
+        }
+
+        let central_directory_size = self.writer.count() - central_directory_offset;
+        as_u32(central_directory_size, "central directory size")?;
+
+        self.writer.write_u32(END_OF_CENTRAL_DIRECTORY_SIGNATURE)?;
+        self.writer.write_u16(0)?; // disk number
+        self.writer.write_u16(0)?; // disk with central directory
+        self.writer.write_u16(self.entries.len() as u16)?;
+        self.writer.write_u16(self.entries.len() as u16)?;
+        self.writer.write_u32(central_directory_size as u32)?;
+        self.writer.write_u32(central_directory_offset as u32)?;
+        self.writer.write_u16(0)?; // comment length
+
+        Ok(self.writer.into_inner())
+    }
+}
+
+/// Thin wrapper that tracks how many bytes have been written so far, since
+/// local file headers need to record their own offset into the archive.
+/// Kept as `u64` so the running total itself never wraps, even though the
+/// emitted format is classic ZIP and individual fields are checked against
+/// the 4 GiB limit before being narrowed to `u32`.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    fn count(&self) -> u64 {
+        self.count
+    }
+
+    fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_u16(&mut self, value: u16) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,6 +1,22 @@
 use anyhow::*;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::file::{NASFile, NASFileCategory};
+
+/// A soft-deleted path, recording where it used to live and where it was
+/// moved to under the per-user `.trash` directory.
+#[derive(Debug, Serialize)]
+pub struct TrashEntry {
+    pub id: i64,
+    pub original_relative_path: String,
+    pub username: String,
+    pub trash_relative_path: String,
+    pub deleted_at: i64,
+}
 
 #[derive(Debug)]
 pub struct NASDB(pub Connection);
@@ -39,6 +55,323 @@ impl NASDB {
             params![],
         )?;
 
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS Files (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                relative_path   TEXT NOT NULL UNIQUE,
+                username        TEXT NOT NULL,
+                name            TEXT NOT NULL,
+                category        TEXT NOT NULL,
+                extension       TEXT NOT NULL,
+                size_bytes      INTEGER NOT NULL,
+                mtime           INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        connection.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS FilesIndex USING fts5(
+                name,
+                relative_path,
+                content='Files',
+                content_rowid='id'
+            )",
+            params![],
+        )?;
+
+        // Keep FilesIndex in sync whenever Files changes, so callers only
+        // ever have to write to Files.
+        connection.execute_batch(
+            "CREATE TRIGGER IF NOT EXISTS Files_ai AFTER INSERT ON Files BEGIN
+                INSERT INTO FilesIndex(rowid, name, relative_path) VALUES (new.id, new.name, new.relative_path);
+            END;
+            CREATE TRIGGER IF NOT EXISTS Files_ad AFTER DELETE ON Files BEGIN
+                INSERT INTO FilesIndex(FilesIndex, rowid, name, relative_path) VALUES ('delete', old.id, old.name, old.relative_path);
+            END;
+            CREATE TRIGGER IF NOT EXISTS Files_au AFTER UPDATE ON Files BEGIN
+                INSERT INTO FilesIndex(FilesIndex, rowid, name, relative_path) VALUES ('delete', old.id, old.name, old.relative_path);
+                INSERT INTO FilesIndex(rowid, name, relative_path) VALUES (new.id, new.name, new.relative_path);
+            END;",
+        )?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS Trash (
+                id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+                original_relative_path  TEXT NOT NULL,
+                username                TEXT NOT NULL,
+                trash_relative_path     TEXT NOT NULL UNIQUE,
+                deleted_at              INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
         Ok(())
     }
+}
+
+impl NASDB {
+    /// Walks `ROOT`, upserting one `Files` row per `NASFile` found and
+    /// pruning rows whose paths no longer exist on disk.
+    pub fn reindex(&self) -> Result<()> {
+        let connection = self.connection();
+        let root = Path::new(&crate::CONFIG.fs_root);
+
+        let mut seen_paths = HashSet::new();
+
+        // Don't descend into per-user `.trash` directories: soft-deleted
+        // files must not resurface in search until they're restored.
+        for entry in walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".trash")
+            .filter_map(|entry| entry.ok())
+        {
+            let pathbuf = entry.into_path();
+            if pathbuf == root {
+                continue;
+            }
+
+            let file = match NASFile::from_pathbuf(pathbuf) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+
+            let mtime = file
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or(0);
+
+            // Per-user storage is laid out as `<username>/...` under ROOT, so
+            // the first path component of a ROOT-relative path is its owner.
+            let username = file
+                .relative_path_str
+                .split('/')
+                .next()
+                .unwrap_or("")
+                .to_string();
+
+            connection.execute(
+                "INSERT INTO Files (relative_path, username, name, category, extension, size_bytes, mtime)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(relative_path) DO UPDATE SET
+                    username = excluded.username,
+                    name = excluded.name,
+                    category = excluded.category,
+                    extension = excluded.extension,
+                    size_bytes = excluded.size_bytes,
+                    mtime = excluded.mtime",
+                params![
+                    file.relative_path_str,
+                    username,
+                    file.name,
+                    file.category.as_str(),
+                    file.extension,
+                    file.size_bytes as i64,
+                    mtime,
+                ],
+            )?;
+
+            seen_paths.insert(file.relative_path_str);
+        }
+
+        self.prune_missing(&seen_paths)?;
+
+        Ok(())
+    }
+
+    fn prune_missing(&self, seen_paths: &HashSet<String>) -> Result<()> {
+        let connection = self.connection();
+
+        let indexed_paths: Vec<String> = {
+            let mut stmt = connection.prepare("SELECT relative_path FROM Files")?;
+            stmt.query_map(params![], |row| row.get(0))?
+                .filter_map(|row| row.ok())
+                .collect()
+        };
+
+        for relative_path in indexed_paths {
+            if !seen_paths.contains(&relative_path) {
+                self.remove_path(&relative_path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the indexed row(s) for `relative_path` (and, if it was a
+    /// directory, everything under it). Called after the `delete` handler
+    /// removes a path so search results don't go stale until the next full
+    /// `reindex`.
+    pub fn remove_path(&self, relative_path: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM Files WHERE relative_path = ?1 OR relative_path LIKE ?2",
+            params![relative_path, format!("{}/%", relative_path)],
+        )?;
+
+        Ok(())
+    }
+
+    /// Full-text search over indexed file/directory names and paths,
+    /// scoped to `username` and optionally narrowed to a single category,
+    /// ranked by FTS5 relevance.
+    pub fn search(
+        &self,
+        username: &str,
+        query: &str,
+        category_filter: Option<NASFileCategory>,
+    ) -> Result<Vec<NASFile>> {
+        let connection = self.connection();
+        let category_filter = category_filter.map(|category| category.as_str().to_string());
+
+        let query = Self::escape_fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = connection.prepare(
+            "SELECT Files.relative_path, Files.name, Files.category, Files.extension, Files.size_bytes
+             FROM FilesIndex
+             JOIN Files ON Files.id = FilesIndex.rowid
+             WHERE FilesIndex MATCH ?1
+             AND Files.username = ?2
+             AND (?3 IS NULL OR Files.category = ?3)
+             ORDER BY rank",
+        )?;
+
+        let rows = stmt.query_map(params![query, username, category_filter], |row| {
+            let relative_path: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            let category: String = row.get(2)?;
+            let extension: String = row.get(3)?;
+            let size_bytes: i64 = row.get(4)?;
+
+            Ok((relative_path, name, category, extension, size_bytes))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let (relative_path, name, category, extension, size_bytes) = row?;
+
+            let absolute_path_str = Path::new(&crate::CONFIG.fs_root)
+                .join(&relative_path)
+                .to_str()
+                .unwrap_or_default()
+                .to_string();
+            let mime_type = mime_guess::from_path(&name)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string();
+
+            results.push(NASFile {
+                name,
+                relative_path_str: relative_path,
+                absolute_path_str,
+                category: NASFileCategory::parse(&category),
+                extension,
+                mime_type,
+                size_bytes: size_bytes as u64,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Turns free-form user input into a sequence of quoted FTS5 string
+    /// literals, so operators (`AND`, `OR`, `NOT`, `-`, `^`, `:`, unbalanced
+    /// `"`, ...) in the query are taken as literal text rather than FTS5
+    /// query syntax, which would otherwise make `search` return an `Err`
+    /// instead of results.
+    fn escape_fts_query(query: &str) -> String {
+        query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl NASDB {
+    /// Records that `original_relative_path` was moved to
+    /// `trash_relative_path` by `username` at `deleted_at` (unix seconds).
+    pub fn insert_trash_entry(
+        &self,
+        original_relative_path: &str,
+        username: &str,
+        trash_relative_path: &str,
+        deleted_at: i64,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO Trash (original_relative_path, username, trash_relative_path, deleted_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                original_relative_path,
+                username,
+                trash_relative_path,
+                deleted_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn find_trash_entry(&self, id: i64) -> Result<Option<TrashEntry>> {
+        self.connection()
+            .query_row(
+                "SELECT id, original_relative_path, username, trash_relative_path, deleted_at
+                 FROM Trash WHERE id = ?1",
+                params![id],
+                Self::trash_entry_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// All trash entries belonging to `username`, most recently deleted first.
+    pub fn list_trash(&self, username: &str) -> Result<Vec<TrashEntry>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, original_relative_path, username, trash_relative_path, deleted_at
+             FROM Trash WHERE username = ?1 ORDER BY deleted_at DESC",
+        )?;
+
+        let entries = stmt
+            .query_map(params![username], Self::trash_entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Trash entries older than `older_than_secs` (unix seconds), used by
+    /// the age-based auto-purge to find what's eligible for permanent
+    /// removal.
+    pub fn expired_trash_entries(&self, older_than_secs: i64) -> Result<Vec<TrashEntry>> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, original_relative_path, username, trash_relative_path, deleted_at
+             FROM Trash WHERE deleted_at < ?1",
+        )?;
+
+        let entries = stmt
+            .query_map(params![older_than_secs], Self::trash_entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn remove_trash_entry(&self, id: i64) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM Trash WHERE id = ?1", params![id])?;
+
+        Ok(())
+    }
+
+    fn trash_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<TrashEntry> {
+        Ok(TrashEntry {
+            id: row.get(0)?,
+            original_relative_path: row.get(1)?,
+            username: row.get(2)?,
+            trash_relative_path: row.get(3)?,
+            deleted_at: row.get(4)?,
+        })
+    }
 }
\ No newline at end of file
@@ -13,6 +13,7 @@ pub struct NASFile {
     pub absolute_path_str: String,
     pub category: NASFileCategory,
     pub extension: String,
+    pub mime_type: String,
     pub size_bytes: u64,
 }
 
@@ -42,7 +43,8 @@ impl NASFile {
         let relative_path_str = relative_path_str.to_string();
 
         let name = NASFile::file_name(&pathbuf)?;
-        let category = NASFile::category(&pathbuf);
+        let mime_type = NASFile::mime_type(&pathbuf);
+        let category = NASFile::category(&pathbuf, &mime_type);
         let extension = NASFile::extension(&pathbuf)?;
         let size_bytes = NASFile::size_bytes(&pathbuf)?;
 
@@ -52,6 +54,7 @@ impl NASFile {
             relative_path_str,
             category,
             extension,
+            mime_type,
             size_bytes,
         })
     }
@@ -80,39 +83,44 @@ impl NASFile {
         Ok(file_name.to_string())
     }
 
-    fn category(pathbuf: &PathBuf) -> NASFileCategory {
-        let is_dir = pathbuf.is_dir();
-        let extension = pathbuf.extension();
-
-        if is_dir {
-            NASFileCategory::Directory
-        } else if let Some(e) = extension {
-            if let Some(e) = e.to_str() {
-                match e {
-                    "mp3" => NASFileCategory::Audio,
-
-                    "avi" => NASFileCategory::Video,
-                    "mkv" => NASFileCategory::Video,
-                    "mp4" => NASFileCategory::Video,
-
-                    "m3u8" => NASFileCategory::StreamPlaylist,
-                    "ts" => NASFileCategory::StreamSegment,
+    /// Guesses the MIME essence (e.g. `video/mp4`) from the file's
+    /// extension, falling back to `application/octet-stream`. Directories
+    /// have no meaningful MIME type.
+    fn mime_type(pathbuf: &PathBuf) -> String {
+        if pathbuf.is_dir() {
+            return "".to_string();
+        }
 
-                    "pdf" => NASFileCategory::Document,
-                    "txt" => NASFileCategory::Document,
+        mime_guess::from_path(pathbuf)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string()
+    }
 
-                    "png" => NASFileCategory::Image,
-                    "jpg" => NASFileCategory::Image,
-                    "jpeg" => NASFileCategory::Image,
-                    "webp" => NASFileCategory::Image,
+    /// Derives the coarse `NASFileCategory` from the MIME top-level/subtype,
+    /// with `.m3u8`/`.ts` special-cased ahead of MIME sniffing since HLS
+    /// playlists and segments need their own category regardless of what
+    /// `mime_guess` reports for them.
+    fn category(pathbuf: &PathBuf, mime_type: &str) -> NASFileCategory {
+        if pathbuf.is_dir() {
+            return NASFileCategory::Directory;
+        }
 
-                    _ => NASFileCategory::Unknown,
-                }
-            } else {
-                NASFileCategory::Unknown
+        if let Some(extension) = pathbuf.extension().and_then(|e| e.to_str()) {
+            match extension {
+                "m3u8" => return NASFileCategory::StreamPlaylist,
+                "ts" => return NASFileCategory::StreamSegment,
+                _ => {}
             }
-        } else {
-            NASFileCategory::Unknown
+        }
+
+        match mime_type.split_once('/') {
+            Some(("video", _)) => NASFileCategory::Video,
+            Some(("audio", _)) => NASFileCategory::Audio,
+            Some(("image", _)) => NASFileCategory::Image,
+            Some(("text", _)) => NASFileCategory::Document,
+            Some(("application", "pdf")) => NASFileCategory::Document,
+            _ => NASFileCategory::Unknown,
         }
     }
 
@@ -136,6 +144,22 @@ impl NASFile {
         Ok(extension.to_string())
     }
 
+    /// Re-reads the filesystem metadata for this file on demand.
+    ///
+    /// `size_bytes` is a snapshot taken when the `NASFile` was built; callers
+    /// that need an up-to-date length (e.g. when seeking for a Range
+    /// response) or timestamps should go through this instead.
+    pub fn metadata(&self) -> Result<std::fs::Metadata> {
+        Path::new(&self.absolute_path_str)
+            .metadata()
+            .with_context(|| {
+                format!(
+                    "[NASFile::metadata] Unable to read metadata for {}",
+                    &self.absolute_path_str
+                )
+            })
+    }
+
     fn size_bytes(pathbuf: &PathBuf) -> Result<u64> {
         if pathbuf.is_dir() {
             return Ok(0);
@@ -224,6 +248,37 @@ pub enum NASFileCategory {
     Unknown,
 }
 
+impl NASFileCategory {
+    /// Stable string form stored in the `Files` table's `category` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NASFileCategory::Directory => "directory",
+            NASFileCategory::Audio => "audio",
+            NASFileCategory::Video => "video",
+            NASFileCategory::StreamPlaylist => "stream_playlist",
+            NASFileCategory::StreamSegment => "stream_segment",
+            NASFileCategory::Document => "document",
+            NASFileCategory::Image => "image",
+            NASFileCategory::Unknown => "unknown",
+        }
+    }
+
+    /// Inverse of `as_str`; unrecognized values map to `Unknown` rather than
+    /// failing, since this only ever reads back what `as_str` wrote.
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "directory" => NASFileCategory::Directory,
+            "audio" => NASFileCategory::Audio,
+            "video" => NASFileCategory::Video,
+            "stream_playlist" => NASFileCategory::StreamPlaylist,
+            "stream_segment" => NASFileCategory::StreamSegment,
+            "document" => NASFileCategory::Document,
+            "image" => NASFileCategory::Image,
+            _ => NASFileCategory::Unknown,
+        }
+    }
+}
+
 impl PartialOrd for NASFileCategory {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         if matches!(self, Self::Directory) && matches!(other, Self::Directory) {